@@ -5,12 +5,24 @@
 
 use {
     crate::{
-        comparator::CompareFn, ffi, AsColumnFamilyRef, CStrLike, Options, ReadOptions, WriteBatch,
+        comparator::CompareFn, db::DB, ffi, ffi_util::error_message, AsColumnFamilyRef, CStrLike,
+        DBIterator, DBRawIterator, Error, Options, ReadOptions, Transaction, WriteBatch,
     },
     libc::{c_char, c_uchar, c_void, size_t},
-    std::{cmp::Ordering, ffi::CString, os::raw::c_int, slice},
+    std::{cmp::Ordering, ffi::CString, os::raw::c_int, ptr, slice},
 };
 
+/// Copies an FFI-owned timestamp out-param into an owned `Vec<u8>`, freeing the original
+/// buffer. A null pointer maps to an empty vec rather than `None`.
+unsafe fn vec_from_raw_ts(ts_ptr: *mut c_char, ts_len: size_t) -> Vec<u8> {
+    if ts_ptr.is_null() {
+        return Vec::new();
+    }
+    let ts = slice::from_raw_parts(ts_ptr as *const u8, ts_len).to_vec();
+    ffi::rocksdb_free(ts_ptr as *mut c_void);
+    ts
+}
+
 pub type CompareTsFn = dyn Fn(&[u8], &[u8]) -> Ordering;
 
 pub type CompareWithoutTsFn = dyn Fn(&[u8], bool, &[u8], bool) -> Ordering;
@@ -108,6 +120,13 @@ impl Options {
             ffi::rocksdb_options_set_comparator(self.inner, cmp);
         }
     }
+
+    /// Sets whether user-defined timestamps are persisted to SST files and the WAL.
+    pub fn set_persist_user_defined_timestamps(&mut self, v: bool) {
+        unsafe {
+            ffi::rocksdb_options_set_persist_user_defined_timestamps(self.inner, v as c_uchar);
+        }
+    }
 }
 
 impl ReadOptions {
@@ -123,6 +142,308 @@ impl ReadOptions {
             ffi::rocksdb_readoptions_set_timestamp(self.inner, ptr as *const c_char, len as size_t);
         }
     }
+
+    /// Sets the lower bound of the timestamp range to iterate over; use together with
+    /// [`ReadOptions::set_timestamp`] (the upper bound) to surface every version of a key
+    /// instead of just the newest.
+    pub fn set_iter_start_ts<T: AsRef<[u8]>>(&mut self, ts: T) {
+        // we need to make sure the timestamp bytes live as long as the ReadOptions.
+        // make a copy of it and let it owned by the ReadOptions.
+        let ts = ts.as_ref().to_owned();
+        let ptr = ts.as_ptr();
+        let len = ts.len();
+        self.iter_start_ts = Some(ts);
+
+        unsafe {
+            ffi::rocksdb_readoptions_set_iter_start_ts(
+                self.inner,
+                ptr as *const c_char,
+                len as size_t,
+            );
+        }
+    }
+}
+
+impl DBRawIterator<'_> {
+    /// Returns the timestamp of the entry the iterator is currently positioned at, for a
+    /// column family opened with [`Options::set_comparator_with_ts`]. Empty if the iterator
+    /// is not [`valid`](DBRawIterator::valid).
+    pub fn timestamp(&self) -> &[u8] {
+        if !self.valid() {
+            return &[];
+        }
+
+        unsafe {
+            let mut ts_len: size_t = 0;
+            let ts_ptr = ffi::rocksdb_iter_timestamp(self.inner, &mut ts_len);
+            if ts_ptr.is_null() {
+                &[]
+            } else {
+                slice::from_raw_parts(ts_ptr as *const u8, ts_len)
+            }
+        }
+    }
+}
+
+impl DBIterator<'_> {
+    /// See [`DBRawIterator::timestamp`].
+    pub fn timestamp(&self) -> &[u8] {
+        self.raw.timestamp()
+    }
+}
+
+impl DB {
+    /// Like [`DB::get`], but also returns the timestamp the value was committed at (empty
+    /// if the key was not found).
+    pub fn get_with_ts<K: AsRef<[u8]>>(
+        &self,
+        key: K,
+    ) -> Result<(Option<Vec<u8>>, Vec<u8>), Error> {
+        self.get_with_ts_opt(key, &ReadOptions::default())
+    }
+
+    /// Like [`DB::get_opt`], but also returns the value's committed timestamp.
+    pub fn get_with_ts_opt<K: AsRef<[u8]>>(
+        &self,
+        key: K,
+        readopts: &ReadOptions,
+    ) -> Result<(Option<Vec<u8>>, Vec<u8>), Error> {
+        self.get_cf_with_ts_opt(None::<&crate::ColumnFamily>, key, readopts)
+    }
+
+    /// Like [`DB::get_cf`], but also returns the value's committed timestamp.
+    pub fn get_cf_with_ts<K: AsRef<[u8]>>(
+        &self,
+        cf: &impl AsColumnFamilyRef,
+        key: K,
+    ) -> Result<(Option<Vec<u8>>, Vec<u8>), Error> {
+        self.get_cf_with_ts_opt(Some(cf), key, &ReadOptions::default())
+    }
+
+    /// Like [`DB::get_cf_opt`], but also returns the value's committed timestamp.
+    pub fn get_cf_with_ts_opt<K: AsRef<[u8]>>(
+        &self,
+        cf: Option<&impl AsColumnFamilyRef>,
+        key: K,
+        readopts: &ReadOptions,
+    ) -> Result<(Option<Vec<u8>>, Vec<u8>), Error> {
+        let key = key.as_ref();
+
+        unsafe {
+            let mut val_len: size_t = 0;
+            let mut ts_len: size_t = 0;
+            let mut ts_ptr: *mut c_char = ptr::null_mut();
+            let mut error = ptr::null_mut();
+
+            let val_ptr = match cf {
+                Some(cf) => ffi::rocksdb_get_cf_with_ts(
+                    self.inner,
+                    readopts.inner,
+                    cf.inner(),
+                    key.as_ptr() as *const c_char,
+                    key.len() as size_t,
+                    &mut val_len,
+                    &mut ts_ptr,
+                    &mut ts_len,
+                    &mut error,
+                ),
+                None => ffi::rocksdb_get_with_ts(
+                    self.inner,
+                    readopts.inner,
+                    key.as_ptr() as *const c_char,
+                    key.len() as size_t,
+                    &mut val_len,
+                    &mut ts_ptr,
+                    &mut ts_len,
+                    &mut error,
+                ),
+            };
+
+            if !error.is_null() {
+                return Err(Error::new(error_message(error)));
+            }
+
+            let timestamp = vec_from_raw_ts(ts_ptr, ts_len);
+            let value = if val_ptr.is_null() {
+                None
+            } else {
+                let value = slice::from_raw_parts(val_ptr as *const u8, val_len).to_vec();
+                ffi::rocksdb_free(val_ptr as *mut c_void);
+                Some(value)
+            };
+
+            Ok((value, timestamp))
+        }
+    }
+
+    /// Raises the low-water mark below which historical versions of keys in `cf` may be
+    /// dropped by compaction. `ts` must be monotonically non-decreasing relative to the
+    /// column family's current low-water mark.
+    pub fn increase_full_history_ts_low<T: AsRef<[u8]>>(
+        &self,
+        cf: &impl AsColumnFamilyRef,
+        ts: T,
+    ) -> Result<(), Error> {
+        let ts = ts.as_ref();
+
+        unsafe {
+            let mut error = ptr::null_mut();
+            ffi::rocksdb_increase_full_history_ts_low(
+                self.inner,
+                cf.inner(),
+                ts.as_ptr() as *const c_char,
+                ts.len() as size_t,
+                &mut error,
+            );
+            if !error.is_null() {
+                return Err(Error::new(error_message(error)));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the current low-water mark below which historical versions of keys in `cf`
+    /// may have already been dropped by compaction.
+    ///
+    /// See [`DB::increase_full_history_ts_low`].
+    pub fn get_full_history_ts_low(&self, cf: &impl AsColumnFamilyRef) -> Result<Vec<u8>, Error> {
+        unsafe {
+            let mut ts_len: size_t = 0;
+            let mut error = ptr::null_mut();
+            let ts_ptr =
+                ffi::rocksdb_get_full_history_ts_low(self.inner, cf.inner(), &mut ts_len, &mut error);
+            if !error.is_null() {
+                return Err(Error::new(error_message(error)));
+            }
+
+            Ok(vec_from_raw_ts(ts_ptr, ts_len))
+        }
+    }
+
+    /// Like [`DB::multi_get_cf`], but reads every key at the single timestamp carried by
+    /// `readopts` and also returns each value's committed timestamp.
+    pub fn multi_get_cf_with_ts<'a, K, C, I>(
+        &self,
+        keys: I,
+        readopts: &ReadOptions,
+    ) -> Vec<Result<(Option<Vec<u8>>, Vec<u8>), Error>>
+    where
+        K: AsRef<[u8]>,
+        C: AsColumnFamilyRef + 'a,
+        I: IntoIterator<Item = (&'a C, K)>,
+    {
+        let (cfs, keys): (Vec<&'a C>, Vec<K>) = keys.into_iter().unzip();
+
+        let num_keys = keys.len();
+        let cf_handles: Vec<_> = cfs.iter().map(|cf| cf.inner()).collect();
+        let key_ptrs: Vec<_> = keys.iter().map(|k| k.as_ref().as_ptr() as *const c_char).collect();
+        let key_lens: Vec<_> = keys.iter().map(|k| k.as_ref().len() as size_t).collect();
+
+        let mut values: Vec<*mut c_char> = vec![ptr::null_mut(); num_keys];
+        let mut value_lens: Vec<size_t> = vec![0; num_keys];
+        let mut timestamps: Vec<*mut c_char> = vec![ptr::null_mut(); num_keys];
+        let mut timestamp_lens: Vec<size_t> = vec![0; num_keys];
+        let mut errors: Vec<*mut c_char> = vec![ptr::null_mut(); num_keys];
+
+        unsafe {
+            ffi::rocksdb_multi_get_cf_with_ts(
+                self.inner,
+                readopts.inner,
+                cf_handles.as_ptr(),
+                num_keys,
+                key_ptrs.as_ptr(),
+                key_lens.as_ptr(),
+                values.as_mut_ptr(),
+                value_lens.as_mut_ptr(),
+                timestamps.as_mut_ptr(),
+                timestamp_lens.as_mut_ptr(),
+                errors.as_mut_ptr(),
+            );
+
+            (0..num_keys)
+                .map(|i| {
+                    if !errors[i].is_null() {
+                        return Err(Error::new(error_message(errors[i])));
+                    }
+
+                    let timestamp = vec_from_raw_ts(timestamps[i], timestamp_lens[i]);
+                    let value = if values[i].is_null() {
+                        None
+                    } else {
+                        let value =
+                            slice::from_raw_parts(values[i] as *const u8, value_lens[i]).to_vec();
+                        ffi::rocksdb_free(values[i] as *mut c_void);
+                        Some(value)
+                    };
+
+                    Ok((value, timestamp))
+                })
+                .collect()
+        }
+    }
+
+    /// Tombstones every key in `[from, to)` of `cf` as of timestamp `ts`.
+    pub fn delete_range_cf_with_ts<K: AsRef<[u8]>, T: AsRef<[u8]>>(
+        &self,
+        cf: &impl AsColumnFamilyRef,
+        from: K,
+        to: K,
+        ts: T,
+    ) -> Result<(), Error> {
+        let from = from.as_ref();
+        let to = to.as_ref();
+        let ts = ts.as_ref();
+
+        unsafe {
+            let mut error = ptr::null_mut();
+            ffi::rocksdb_delete_range_cf_with_ts(
+                self.inner,
+                cf.inner(),
+                from.as_ptr() as *const c_char,
+                from.len() as size_t,
+                to.as_ptr() as *const c_char,
+                to.len() as size_t,
+                ts.as_ptr() as *const c_char,
+                ts.len() as size_t,
+                &mut error,
+            );
+            if !error.is_null() {
+                return Err(Error::new(error_message(error)));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Like [`DB::delete_cf`], but records a single-deletion tombstone at timestamp `ts`.
+    pub fn single_delete_cf_with_ts<K: AsRef<[u8]>, T: AsRef<[u8]>>(
+        &self,
+        cf: &impl AsColumnFamilyRef,
+        key: K,
+        ts: T,
+    ) -> Result<(), Error> {
+        let key = key.as_ref();
+        let ts = ts.as_ref();
+
+        unsafe {
+            let mut error = ptr::null_mut();
+            ffi::rocksdb_single_delete_cf_with_ts(
+                self.inner,
+                cf.inner(),
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+                ts.as_ptr() as *const c_char,
+                ts.len() as size_t,
+                &mut error,
+            );
+            if !error.is_null() {
+                return Err(Error::new(error_message(error)));
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl WriteBatch {
@@ -169,4 +490,54 @@ impl WriteBatch {
             );
         }
     }
+
+    pub fn delete_range_cf_with_ts<K, T>(
+        &mut self,
+        cf: &impl AsColumnFamilyRef,
+        from: K,
+        to: K,
+        ts: T,
+    ) where
+        K: AsRef<[u8]>,
+        T: AsRef<[u8]>,
+    {
+        let from = from.as_ref();
+        let to = to.as_ref();
+        let ts = ts.as_ref();
+
+        unsafe {
+            ffi::rocksdb_writebatch_delete_range_cf_with_ts(
+                self.inner,
+                cf.inner(),
+                from.as_ptr() as *const c_char,
+                from.len() as size_t,
+                to.as_ptr() as *const c_char,
+                to.len() as size_t,
+                ts.as_ptr() as *const c_char,
+                ts.len() as size_t,
+            );
+        }
+    }
+}
+
+impl<'db, D> Transaction<'db, D> {
+    /// Sets the timestamp this transaction will commit at; the column family being written
+    /// to must use [`Options::set_comparator_with_ts`].
+    pub fn set_commit_timestamp<T: AsRef<[u8]>>(&self, ts: T) {
+        let ts = ts.as_ref();
+
+        unsafe {
+            ffi::rocksdb_transaction_set_commit_timestamp(
+                self.inner,
+                ts.as_ptr() as *const c_char,
+                ts.len() as size_t,
+            );
+        }
+    }
+
+    /// Equivalent to [`Transaction::set_commit_timestamp`] followed by [`Transaction::commit`].
+    pub fn commit_with_ts<T: AsRef<[u8]>>(self, ts: T) -> Result<(), Error> {
+        self.set_commit_timestamp(ts);
+        self.commit()
+    }
 }
@@ -1,10 +1,38 @@
 mod util;
 
 use {
-    rocksdb::{Options, ReadOptions, DB},
+    rocksdb::{
+        OptimisticTransactionDB, Options, ReadOptions, TransactionDB, TransactionDBOptions, DB,
+    },
     util::DBPath,
 };
 
+/// Configures `opts` with a user-defined-timestamp comparator for an 8-byte big-endian
+/// `u64` timestamp, which is what every test in this file writes. Shared so each test
+/// doesn't have to repeat the full 5-argument `set_comparator_with_ts` call.
+fn set_u64_ts_comparator(opts: &mut Options) {
+    opts.set_comparator_with_ts(
+        "test_u64_ts",
+        8,
+        // Orders by user key, then by timestamp with the newest version first.
+        Box::new(|a, b| {
+            let (a_key, a_ts) = a.split_at(a.len() - 8);
+            let (b_key, b_ts) = b.split_at(b.len() - 8);
+            a_key.cmp(b_key).then_with(|| b_ts.cmp(a_ts))
+        }),
+        Box::new(|a, b| {
+            let a = u64::from_be_bytes(a.try_into().unwrap());
+            let b = u64::from_be_bytes(b.try_into().unwrap());
+            a.cmp(&b)
+        }),
+        Box::new(|a, a_has_ts, b, b_has_ts| {
+            let a = if a_has_ts { &a[..a.len() - 8] } else { a };
+            let b = if b_has_ts { &b[..b.len() - 8] } else { b };
+            a.cmp(b)
+        }),
+    );
+}
+
 #[test]
 fn timestamping_works() {
     let path = DBPath::new("_rust_rocksdb_timestamping_works");
@@ -12,7 +40,7 @@ fn timestamping_works() {
     let mut db_opts = Options::default();
     db_opts.create_if_missing(true);
     db_opts.create_missing_column_families(true);
-    db_opts.set_comparator_with_ts("cname", Box::new(|a, b| a.cmp(b)));
+    set_u64_ts_comparator(&mut db_opts);
 
     let db = DB::open(&db_opts, &path).unwrap();
 
@@ -31,3 +59,296 @@ fn timestamping_works() {
     let value = String::from_utf8(db.get_opt("fish", &read_opts).unwrap().unwrap()).unwrap();
     assert_eq!(value, "tuna");
 }
+
+#[test]
+fn get_with_ts_returns_committed_timestamp() {
+    let path = DBPath::new("_rust_rocksdb_get_with_ts_returns_committed_timestamp");
+
+    let mut db_opts = Options::default();
+    db_opts.create_if_missing(true);
+    db_opts.create_missing_column_families(true);
+    set_u64_ts_comparator(&mut db_opts);
+
+    let db = DB::open(&db_opts, &path).unwrap();
+
+    let ts1 = 1_u64.to_be_bytes();
+    db.put_with_ts("fish", ts1, "tuna").unwrap();
+
+    let mut read_opts = ReadOptions::default();
+    read_opts.set_timestamp(2_u64.to_be_bytes());
+
+    let (value, ts) = db.get_with_ts_opt("fish", &read_opts).unwrap();
+    assert_eq!(value.unwrap(), b"tuna");
+    assert_eq!(ts, ts1);
+
+    let (value, ts) = db.get_with_ts_opt("shark", &read_opts).unwrap();
+    assert_eq!(value, None);
+    assert!(ts.is_empty());
+}
+
+#[test]
+fn iter_start_ts_yields_every_version() {
+    let path = DBPath::new("_rust_rocksdb_iter_start_ts_yields_every_version");
+
+    let mut db_opts = Options::default();
+    db_opts.create_if_missing(true);
+    db_opts.create_missing_column_families(true);
+    set_u64_ts_comparator(&mut db_opts);
+
+    let db = DB::open(&db_opts, &path).unwrap();
+
+    let ts1 = 1_u64.to_be_bytes();
+    let ts2 = 2_u64.to_be_bytes();
+    let ts3 = 3_u64.to_be_bytes();
+    db.put_with_ts("fish", ts1, "tuna").unwrap();
+    db.put_with_ts("fish", ts2, "sardine").unwrap();
+    db.put_with_ts("fish", ts3, "salmon").unwrap();
+
+    let mut read_opts = ReadOptions::default();
+    read_opts.set_timestamp(ts3);
+    read_opts.set_iter_start_ts(ts1);
+
+    let mut iter = db.raw_iterator_opt(read_opts);
+    iter.seek_to_first();
+
+    let mut versions = Vec::new();
+    while iter.valid() {
+        let value = String::from_utf8(iter.value().unwrap().to_vec()).unwrap();
+        let ts = iter.timestamp().to_vec();
+        versions.push((ts, value));
+        iter.next();
+    }
+
+    assert_eq!(
+        versions,
+        vec![
+            (ts3.to_vec(), "salmon".to_string()),
+            (ts2.to_vec(), "sardine".to_string()),
+            (ts1.to_vec(), "tuna".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn full_history_ts_low_gcs_old_versions() {
+    let path = DBPath::new("_rust_rocksdb_full_history_ts_low_gcs_old_versions");
+
+    let mut db_opts = Options::default();
+    db_opts.create_if_missing(true);
+    db_opts.create_missing_column_families(true);
+    set_u64_ts_comparator(&mut db_opts);
+
+    let db = DB::open(&db_opts, &path).unwrap();
+    let cf = db.cf_handle("default").unwrap();
+
+    let ts1 = 1_u64.to_be_bytes();
+    let ts2 = 2_u64.to_be_bytes();
+    let ts3 = 3_u64.to_be_bytes();
+    db.put_with_ts("fish", ts1, "tuna").unwrap();
+    db.put_with_ts("fish", ts2, "sardine").unwrap();
+    db.put_with_ts("fish", ts3, "salmon").unwrap();
+
+    assert_eq!(db.get_full_history_ts_low(&cf).unwrap(), Vec::<u8>::new());
+
+    db.increase_full_history_ts_low(&cf, ts2).unwrap();
+    assert_eq!(db.get_full_history_ts_low(&cf).unwrap(), ts2);
+
+    db.compact_range(None::<&[u8]>, None::<&[u8]>);
+
+    let mut read_opts = ReadOptions::default();
+    read_opts.set_timestamp(ts1);
+    assert!(db.get_opt("fish", &read_opts).is_err());
+
+    let mut read_opts = ReadOptions::default();
+    read_opts.set_timestamp(ts3);
+    let value = String::from_utf8(db.get_opt("fish", &read_opts).unwrap().unwrap()).unwrap();
+    assert_eq!(value, "salmon");
+}
+
+#[test]
+fn persist_user_defined_timestamps_disabled_still_gcs_below_low_water_mark() {
+    let path = DBPath::new(
+        "_rust_rocksdb_persist_user_defined_timestamps_disabled_still_gcs_below_low_water_mark",
+    );
+
+    let mut db_opts = Options::default();
+    db_opts.create_if_missing(true);
+    db_opts.create_missing_column_families(true);
+    set_u64_ts_comparator(&mut db_opts);
+    db_opts.set_persist_user_defined_timestamps(false);
+
+    let db = DB::open(&db_opts, &path).unwrap();
+    let cf = db.cf_handle("default").unwrap();
+
+    let ts1 = 1_u64.to_be_bytes();
+    let ts2 = 2_u64.to_be_bytes();
+    let ts3 = 3_u64.to_be_bytes();
+    db.put_with_ts("fish", ts1, "tuna").unwrap();
+    db.put_with_ts("fish", ts2, "sardine").unwrap();
+    db.put_with_ts("fish", ts3, "salmon").unwrap();
+
+    db.increase_full_history_ts_low(&cf, ts2).unwrap();
+    db.compact_range(None::<&[u8]>, None::<&[u8]>);
+
+    let mut read_opts = ReadOptions::default();
+    read_opts.set_timestamp(ts1);
+    assert!(db.get_opt("fish", &read_opts).is_err());
+
+    let mut read_opts = ReadOptions::default();
+    read_opts.set_timestamp(ts3);
+    let value = String::from_utf8(db.get_opt("fish", &read_opts).unwrap().unwrap()).unwrap();
+    assert_eq!(value, "salmon");
+}
+
+#[test]
+fn multi_get_cf_with_ts_reads_many_keys_at_one_snapshot() {
+    let path = DBPath::new("_rust_rocksdb_multi_get_cf_with_ts_reads_many_keys_at_one_snapshot");
+
+    let mut db_opts = Options::default();
+    db_opts.create_if_missing(true);
+    db_opts.create_missing_column_families(true);
+    set_u64_ts_comparator(&mut db_opts);
+
+    let db = DB::open(&db_opts, &path).unwrap();
+    let cf = db.cf_handle("default").unwrap();
+
+    let ts1 = 1_u64.to_be_bytes();
+    db.put_with_ts("fish", ts1, "tuna").unwrap();
+    db.put_with_ts("bird", ts1, "robin").unwrap();
+
+    let mut read_opts = ReadOptions::default();
+    read_opts.set_timestamp(ts1);
+
+    let results = db.multi_get_cf_with_ts(
+        vec![(&cf, "fish"), (&cf, "bird"), (&cf, "missing")],
+        &read_opts,
+    );
+
+    let (fish, fish_ts) = results[0].as_ref().unwrap();
+    assert_eq!(fish.as_deref(), Some("tuna".as_bytes()));
+    assert_eq!(fish_ts, &ts1);
+
+    let (bird, bird_ts) = results[1].as_ref().unwrap();
+    assert_eq!(bird.as_deref(), Some("robin".as_bytes()));
+    assert_eq!(bird_ts, &ts1);
+
+    let (missing, missing_ts) = results[2].as_ref().unwrap();
+    assert_eq!(*missing, None);
+    assert!(missing_ts.is_empty());
+}
+
+#[test]
+fn transaction_commits_at_chosen_timestamp() {
+    let path = DBPath::new("_rust_rocksdb_transaction_commits_at_chosen_timestamp");
+
+    let mut db_opts = Options::default();
+    db_opts.create_if_missing(true);
+    db_opts.create_missing_column_families(true);
+    set_u64_ts_comparator(&mut db_opts);
+
+    let txn_db_opts = TransactionDBOptions::default();
+    let db: TransactionDB = TransactionDB::open(&db_opts, &txn_db_opts, &path).unwrap();
+
+    let ts1 = 1_u64.to_be_bytes();
+    let txn = db.transaction();
+    txn.put("fish", "tuna").unwrap();
+    txn.commit_with_ts(ts1).unwrap();
+
+    let mut read_opts = ReadOptions::default();
+    read_opts.set_timestamp(ts1);
+
+    let (value, ts) = db.get_with_ts_opt("fish", &read_opts).unwrap();
+    assert_eq!(value.unwrap(), b"tuna");
+    assert_eq!(ts, ts1);
+}
+
+#[test]
+fn optimistic_transaction_commits_at_chosen_timestamp() {
+    let path = DBPath::new("_rust_rocksdb_optimistic_transaction_commits_at_chosen_timestamp");
+
+    let mut db_opts = Options::default();
+    db_opts.create_if_missing(true);
+    db_opts.create_missing_column_families(true);
+    set_u64_ts_comparator(&mut db_opts);
+
+    let db: OptimisticTransactionDB = OptimisticTransactionDB::open(&db_opts, &path).unwrap();
+
+    let ts1 = 1_u64.to_be_bytes();
+    let txn = db.transaction();
+    txn.put("fish", "tuna").unwrap();
+    txn.commit_with_ts(ts1).unwrap();
+
+    let mut read_opts = ReadOptions::default();
+    read_opts.set_timestamp(ts1);
+
+    let (value, ts) = db.get_with_ts_opt("fish", &read_opts).unwrap();
+    assert_eq!(value.unwrap(), b"tuna");
+    assert_eq!(ts, ts1);
+}
+
+#[test]
+fn delete_range_cf_with_ts_tombstones_at_the_given_timestamp() {
+    let path = DBPath::new("_rust_rocksdb_delete_range_cf_with_ts_tombstones_at_the_given_timestamp");
+
+    let mut db_opts = Options::default();
+    db_opts.create_if_missing(true);
+    db_opts.create_missing_column_families(true);
+    set_u64_ts_comparator(&mut db_opts);
+
+    let db = DB::open(&db_opts, &path).unwrap();
+    let cf = db.cf_handle("default").unwrap();
+
+    let ts1 = 1_u64.to_be_bytes();
+    let ts4 = 4_u64.to_be_bytes();
+    let ts5 = 5_u64.to_be_bytes();
+    let ts6 = 6_u64.to_be_bytes();
+
+    db.put_with_ts("fish", ts1, "tuna").unwrap();
+    db.put_with_ts("goat", ts1, "billy").unwrap();
+
+    db.delete_range_cf_with_ts(&cf, "fish", "goaa", ts5).unwrap();
+
+    let mut read_opts = ReadOptions::default();
+    read_opts.set_timestamp(ts4);
+    let value = String::from_utf8(db.get_opt("fish", &read_opts).unwrap().unwrap()).unwrap();
+    assert_eq!(value, "tuna");
+    let value = String::from_utf8(db.get_opt("goat", &read_opts).unwrap().unwrap()).unwrap();
+    assert_eq!(value, "billy");
+
+    let mut read_opts = ReadOptions::default();
+    read_opts.set_timestamp(ts6);
+    assert_eq!(db.get_opt("fish", &read_opts).unwrap(), None);
+    let value = String::from_utf8(db.get_opt("goat", &read_opts).unwrap().unwrap()).unwrap();
+    assert_eq!(value, "billy");
+}
+
+#[test]
+fn single_delete_cf_with_ts_tombstones_at_the_given_timestamp() {
+    let path = DBPath::new("_rust_rocksdb_single_delete_cf_with_ts_tombstones_at_the_given_timestamp");
+
+    let mut db_opts = Options::default();
+    db_opts.create_if_missing(true);
+    db_opts.create_missing_column_families(true);
+    set_u64_ts_comparator(&mut db_opts);
+
+    let db = DB::open(&db_opts, &path).unwrap();
+    let cf = db.cf_handle("default").unwrap();
+
+    let ts1 = 1_u64.to_be_bytes();
+    let ts4 = 4_u64.to_be_bytes();
+    let ts5 = 5_u64.to_be_bytes();
+    let ts6 = 6_u64.to_be_bytes();
+
+    db.put_with_ts("fish", ts1, "tuna").unwrap();
+    db.single_delete_cf_with_ts(&cf, "fish", ts5).unwrap();
+    db.compact_range(None::<&[u8]>, None::<&[u8]>);
+
+    let mut read_opts = ReadOptions::default();
+    read_opts.set_timestamp(ts4);
+    let value = String::from_utf8(db.get_opt("fish", &read_opts).unwrap().unwrap()).unwrap();
+    assert_eq!(value, "tuna");
+
+    let mut read_opts = ReadOptions::default();
+    read_opts.set_timestamp(ts6);
+    assert_eq!(db.get_opt("fish", &read_opts).unwrap(), None);
+}